@@ -1,33 +1,218 @@
-use rdev::{EventType, Key, listen};
-use std::collections::HashSet;
+use rdev::{EventType, Key, grab};
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
-// Enum to define the two types of shortcuts you wanted.
+/// Why a tap-dance resolved: either the rolling timeout elapsed, or a
+/// different key interrupted it before the timeout was up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapDanceEnd {
+    Timeout,
+    OtherKey,
+}
+
+/// A logical modifier, normalized across its left/right (or AltGr) physical
+/// variants so that e.g. `CMD+S` matches `MetaLeft` and `MetaRight` alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Modifier {
+    Shift,
+    Control,
+    Alt,
+    Meta,
+}
+
+impl Modifier {
+    /// Maps a raw `rdev::Key` to the logical modifier it represents, if any.
+    fn from_key(key: Key) -> Option<Self> {
+        match key {
+            Key::ShiftLeft | Key::ShiftRight => Some(Modifier::Shift),
+            Key::ControlLeft | Key::ControlRight => Some(Modifier::Control),
+            Key::Alt | Key::AltGr => Some(Modifier::Alt),
+            Key::MetaLeft | Key::MetaRight => Some(Modifier::Meta),
+            _ => None,
+        }
+    }
+}
+
+/// A side-agnostic set of required modifiers for a combination, e.g.
+/// `ModifierSet::new().with(Modifier::Meta)` for "CMD" on either side.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModifierSet {
+    shift: bool,
+    control: bool,
+    alt: bool,
+    meta: bool,
+}
+
+impl ModifierSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a copy of this set with `modifier` required.
+    pub fn with(mut self, modifier: Modifier) -> Self {
+        match modifier {
+            Modifier::Shift => self.shift = true,
+            Modifier::Control => self.control = true,
+            Modifier::Alt => self.alt = true,
+            Modifier::Meta => self.meta = true,
+        }
+        self
+    }
+
+    /// Computes the logical modifiers currently held down from the raw set
+    /// of physically pressed keys.
+    fn from_pressed_keys(pressed_keys: &HashSet<Key>) -> Self {
+        let mut modifiers = Self::default();
+        for key in pressed_keys {
+            match Modifier::from_key(*key) {
+                Some(Modifier::Shift) => modifiers.shift = true,
+                Some(Modifier::Control) => modifiers.control = true,
+                Some(Modifier::Alt) => modifiers.alt = true,
+                Some(Modifier::Meta) => modifiers.meta = true,
+                None => {}
+            }
+        }
+        modifiers
+    }
+
+    /// Returns true if every modifier required by `self` is present in `other`.
+    fn is_satisfied_by(&self, other: &Self) -> bool {
+        (!self.shift || other.shift)
+            && (!self.control || other.control)
+            && (!self.alt || other.alt)
+            && (!self.meta || other.meta)
+    }
+
+    /// Returns true if `modifier` is one of the modifiers required by `self`.
+    fn requires(&self, modifier: Modifier) -> bool {
+        match modifier {
+            Modifier::Shift => self.shift,
+            Modifier::Control => self.control,
+            Modifier::Alt => self.alt,
+            Modifier::Meta => self.meta,
+        }
+    }
+}
+
+// Enum to define the types of shortcuts you wanted. Each variant owns the
+// callback(s) relevant to its own semantics, since a tap-dance needs one
+// callback per tap count rather than a single one.
 enum ShortcutType {
     // A set of keys that must be pressed at the same time.
-    Combination(HashSet<Key>),
+    Combination {
+        keys: HashSet<Key>,
+        // When true, a matching press is swallowed and never reaches the
+        // focused application.
+        consume: bool,
+        // None: fire once on the not-satisfied -> satisfied transition and
+        // ignore the OS's auto-repeat presses for as long as it stays held.
+        // Some(interval): keep firing at a fixed cadence while held instead.
+        repeat: Option<Duration>,
+        callback: Box<dyn Fn() + Send + Sync>,
+    },
+    // A modifier-agnostic combination, e.g. `{Meta}+S`, that matches
+    // regardless of which physical left/right key provided each modifier.
+    ModifierCombination {
+        modifiers: ModifierSet,
+        key: Key,
+        consume: bool,
+        // Same semantics as `Combination::repeat`: `None` fires once on the
+        // not-satisfied -> satisfied transition, `Some(interval)` keeps
+        // firing at that cadence while held.
+        repeat: Option<Duration>,
+        callback: Box<dyn Fn() + Send + Sync>,
+    },
     // A single key that must be pressed twice in succession within a given timeout.
-    Succession { key: Key, timeout: Duration },
+    Succession {
+        key: Key,
+        timeout: Duration,
+        consume: bool,
+        callback: Box<dyn Fn() + Send + Sync>,
+    },
+    // A sequence of keys that must be pressed one after another, each within
+    // `timeout` of the previous (e.g. "g g" or "j k" in Zed/Vim-style editors).
+    Sequence {
+        keys: Vec<Key>,
+        timeout: Duration,
+        callback: Box<dyn Fn() + Send + Sync>,
+    },
+    // The same key pressed N times within a rolling `timeout`, dispatching to
+    // the handler registered for the final tap count.
+    TapDance {
+        key: Key,
+        timeout: Duration,
+        handlers: HashMap<u32, Box<dyn Fn(TapDanceEnd) + Send + Sync>>,
+    },
+    // A dual-role key: tapped quickly it fires `on_tap`, held past
+    // `hold_threshold` it fires `on_hold` instead (e.g. Space-as-layer).
+    TapHold {
+        key: Key,
+        hold_threshold: Duration,
+        on_tap: Box<dyn Fn() + Send + Sync>,
+        on_hold: Box<dyn Fn() + Send + Sync>,
+    },
 }
 
-// This struct holds the information for a registered shortcut.
-struct Shortcut {
+/// Opaque handle to a registered shortcut, returned by every `register_*`
+/// method. Pass it to `unregister` or `set_enabled` to manage that shortcut
+/// later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShortcutHandle(u64);
+
+/// A registered shortcut plus the bookkeeping needed to unregister, disable,
+/// or scope it to a named context.
+struct ShortcutEntry {
+    id: u64,
+    // The named context this shortcut was registered under, if any (see
+    // `push_context`/`pop_context`). `None` means it always matches.
+    context: Option<String>,
+    enabled: bool,
     shortcut_type: ShortcutType,
-    // The code to run when the shortcut is triggered.
-    callback: Box<dyn Fn() + Send + Sync>,
+}
+
+/// Tracks an in-progress tap-dance for a given key: how many taps have been
+/// seen so far, and a generation counter so a stale timer (superseded by a
+/// newer tap or a different key) knows to no-op instead of firing late.
+struct TapDanceState {
+    count: u32,
+    generation: u64,
+}
+
+/// Tracks an in-progress tap-hold for a given key: only the generation is
+/// needed, so a stale timer from an earlier press/release cycle on the same
+/// key can tell it has been superseded and should no-op.
+struct TapHoldState {
+    generation: u64,
 }
 
 /// Manages keyboard shortcuts and listens for their activation.
 pub struct KeyboardManager {
-    shortcuts: Arc<Mutex<Vec<Shortcut>>>,
+    shortcuts: Arc<Mutex<Vec<ShortcutEntry>>>,
+    // Backs each new `ShortcutHandle`.
+    next_id: Arc<Mutex<u64>>,
+    // The stack of currently pushed context names; the top is the active one.
+    context_stack: Arc<Mutex<Vec<String>>>,
     // Keeps track of the keys currently being held down.
     pressed_keys: Arc<Mutex<HashSet<Key>>>,
     // Records the time of the last key press to check for succession shortcuts.
     last_key_press_time: Arc<Mutex<Option<Instant>>>,
     // Records the last key that was pressed to ensure succession is direct.
     last_key_pressed: Arc<Mutex<Option<Key>>>,
+    // Buffers recent keystrokes (with their timestamps) that could still
+    // complete a registered `Sequence`.
+    pending_sequence: Arc<Mutex<Vec<(Key, Instant)>>>,
+    // Tracks in-progress tap-dances, keyed by the key being danced on.
+    pending_tapdance: Arc<Mutex<HashMap<Key, TapDanceState>>>,
+    // Tracks in-progress tap-holds, keyed by the key being tapped or held.
+    pending_taphold: Arc<Mutex<HashMap<Key, TapHoldState>>>,
+    // Monotonic counter backing each tap-hold's generation.
+    taphold_generation: Arc<Mutex<u64>>,
+    // Tracks which `Combination` shortcuts (by shortcut id) are currently
+    // fully held, along with when each last fired, so auto-repeat
+    // key-presses don't re-trigger it outside its configured `repeat` cadence.
+    active_combinations: Arc<Mutex<HashMap<u64, Instant>>>,
 }
 
 impl KeyboardManager {
@@ -35,72 +220,560 @@ impl KeyboardManager {
     pub fn new() -> Self {
         Self {
             shortcuts: Arc::new(Mutex::new(Vec::new())),
+            next_id: Arc::new(Mutex::new(0)),
+            context_stack: Arc::new(Mutex::new(Vec::new())),
             pressed_keys: Arc::new(Mutex::new(HashSet::new())),
             last_key_press_time: Arc::new(Mutex::new(None)),
             last_key_pressed: Arc::new(Mutex::new(None)),
+            pending_sequence: Arc::new(Mutex::new(Vec::new())),
+            pending_tapdance: Arc::new(Mutex::new(HashMap::new())),
+            pending_taphold: Arc::new(Mutex::new(HashMap::new())),
+            taphold_generation: Arc::new(Mutex::new(0)),
+            active_combinations: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Pushes a named context. Shortcuts registered while `name` is on top of
+    /// the stack only match while `name` remains the active (topmost)
+    /// context, letting an application model exclusive modes (e.g. an
+    /// editor's "insert" vs "normal" keymaps) instead of one flat global set.
+    pub fn push_context(&mut self, name: impl Into<String>) {
+        self.context_stack.lock().unwrap().push(name.into());
+    }
+
+    /// Pops the most recently pushed context, returning its name if any.
+    pub fn pop_context(&mut self) -> Option<String> {
+        self.context_stack.lock().unwrap().pop()
+    }
+
+    /// Unregisters a previously registered shortcut. No-op if it was already
+    /// unregistered.
+    pub fn unregister(&mut self, handle: ShortcutHandle) {
+        self.shortcuts
+            .lock()
+            .unwrap()
+            .retain(|entry| entry.id != handle.0);
+        // Drop it from `active_combinations` directly rather than relying on
+        // the release-time lookup against `shortcuts`: if this was a
+        // currently-held Combination or ModifierCombination, that lookup
+        // would no longer find it (it's just been removed above) and the
+        // stale entry would never get cleared.
+        self.active_combinations.lock().unwrap().remove(&handle.0);
+    }
+
+    /// Enables or disables a previously registered shortcut without removing
+    /// it, so it can be toggled back on later.
+    pub fn set_enabled(&mut self, handle: ShortcutHandle, enabled: bool) {
+        if let Some(entry) = self
+            .shortcuts
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|entry| entry.id == handle.0)
+        {
+            entry.enabled = enabled;
         }
     }
 
-    /// Registers a combination shortcut.
-    pub fn register_combination<F>(&mut self, keys: Vec<Key>, callback: F)
+    /// Wraps `shortcut_type` in a new entry scoped to the current context (if
+    /// any is pushed) and stores it, returning a handle to manage it later.
+    fn register(&mut self, shortcut_type: ShortcutType) -> ShortcutHandle {
+        let mut next_id = self.next_id.lock().unwrap();
+        let id = *next_id;
+        *next_id += 1;
+        drop(next_id);
+
+        let context = self.context_stack.lock().unwrap().last().cloned();
+        self.shortcuts.lock().unwrap().push(ShortcutEntry {
+            id,
+            context,
+            enabled: true,
+            shortcut_type,
+        });
+        ShortcutHandle(id)
+    }
+
+    /// Registers a combination shortcut. When `consume` is true, a matching
+    /// press is swallowed (it will not reach the focused application);
+    /// otherwise it fires the callback and propagates unchanged. Fires once
+    /// when the combination becomes fully held, and is not re-triggered by
+    /// the OS's auto-repeat presses while it stays held; use
+    /// `register_combination_with_repeat` if repeat firing is desired.
+    pub fn register_combination<F>(
+        &mut self,
+        keys: Vec<Key>,
+        consume: bool,
+        callback: F,
+    ) -> ShortcutHandle
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.register(ShortcutType::Combination {
+            keys: keys.into_iter().collect(),
+            consume,
+            repeat: None,
+            callback: Box::new(callback),
+        })
+    }
+
+    /// Registers a combination shortcut that keeps firing at a fixed
+    /// `repeat_interval` cadence for as long as it stays held, instead of
+    /// firing only once on the initial press.
+    pub fn register_combination_with_repeat<F>(
+        &mut self,
+        keys: Vec<Key>,
+        consume: bool,
+        repeat_interval: Duration,
+        callback: F,
+    ) -> ShortcutHandle
     where
         F: Fn() + Send + Sync + 'static,
     {
-        let shortcut = Shortcut {
-            shortcut_type: ShortcutType::Combination(keys.into_iter().collect()),
+        self.register(ShortcutType::Combination {
+            keys: keys.into_iter().collect(),
+            consume,
+            repeat: Some(repeat_interval),
             callback: Box::new(callback),
-        };
-        self.shortcuts.lock().unwrap().push(shortcut);
+        })
     }
 
-    /// Registers a succession shortcut for a key pressed twice in a row.
-    pub fn register_succession<F>(&mut self, key: Key, timeout: Duration, callback: F)
+    /// Registers a modifier-agnostic combination, e.g.
+    /// `ModifierSet::new().with(Modifier::Meta)` plus `Key::KeyS` for "CMD+S"
+    /// that fires regardless of whether the left or right Meta key is held.
+    /// When `consume` is true, a matching press is swallowed. Fires once
+    /// when the combination becomes fully held, and is not re-triggered by
+    /// the OS's auto-repeat presses while it stays held; use
+    /// `register_modifier_combination_with_repeat` if repeat firing is
+    /// desired.
+    pub fn register_modifier_combination<F>(
+        &mut self,
+        modifiers: ModifierSet,
+        key: Key,
+        consume: bool,
+        callback: F,
+    ) -> ShortcutHandle
     where
         F: Fn() + Send + Sync + 'static,
     {
-        let shortcut = Shortcut {
-            shortcut_type: ShortcutType::Succession { key, timeout },
+        self.register(ShortcutType::ModifierCombination {
+            modifiers,
+            key,
+            consume,
+            repeat: None,
             callback: Box::new(callback),
-        };
-        self.shortcuts.lock().unwrap().push(shortcut);
+        })
+    }
+
+    /// Registers a modifier-agnostic combination that keeps firing at a fixed
+    /// `repeat_interval` cadence for as long as it stays held, instead of
+    /// firing only once on the initial press.
+    pub fn register_modifier_combination_with_repeat<F>(
+        &mut self,
+        modifiers: ModifierSet,
+        key: Key,
+        consume: bool,
+        repeat_interval: Duration,
+        callback: F,
+    ) -> ShortcutHandle
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.register(ShortcutType::ModifierCombination {
+            modifiers,
+            key,
+            consume,
+            repeat: Some(repeat_interval),
+            callback: Box::new(callback),
+        })
+    }
+
+    /// Registers a succession shortcut for a key pressed twice in a row. When
+    /// `consume` is true, the second (matching) press is swallowed instead of
+    /// reaching the focused application.
+    pub fn register_succession<F>(
+        &mut self,
+        key: Key,
+        timeout: Duration,
+        consume: bool,
+        callback: F,
+    ) -> ShortcutHandle
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.register(ShortcutType::Succession {
+            key,
+            timeout,
+            consume,
+            callback: Box::new(callback),
+        })
+    }
+
+    /// Registers a chord sequence, e.g. `vec![Key::KeyG, Key::KeyG]` for "g g".
+    /// Each key in the sequence must be pressed within `timeout` of the previous
+    /// one, in order, for the callback to fire.
+    pub fn register_sequence<F>(
+        &mut self,
+        keys: Vec<Key>,
+        timeout: Duration,
+        callback: F,
+    ) -> ShortcutHandle
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.register(ShortcutType::Sequence {
+            keys,
+            timeout,
+            callback: Box::new(callback),
+        })
+    }
+
+    /// Registers a tap-dance on `key`: pressing it repeatedly resolves to the
+    /// handler in `handlers` matching the final tap count, once either
+    /// `timeout` elapses since the last tap or a different key interrupts it.
+    pub fn register_tapdance(
+        &mut self,
+        key: Key,
+        timeout: Duration,
+        handlers: HashMap<u32, Box<dyn Fn(TapDanceEnd) + Send + Sync>>,
+    ) -> ShortcutHandle {
+        self.register(ShortcutType::TapDance {
+            key,
+            timeout,
+            handlers,
+        })
+    }
+
+    /// Registers a dual-role key: tapped and released within `hold_threshold`
+    /// it fires `on_tap`; held past `hold_threshold` (or interrupted by
+    /// another key press before then) it fires `on_hold` instead.
+    pub fn register_tap_hold<T, H>(
+        &mut self,
+        key: Key,
+        hold_threshold: Duration,
+        on_tap: T,
+        on_hold: H,
+    ) -> ShortcutHandle
+    where
+        T: Fn() + Send + Sync + 'static,
+        H: Fn() + Send + Sync + 'static,
+    {
+        self.register(ShortcutType::TapHold {
+            key,
+            hold_threshold,
+            on_tap: Box::new(on_tap),
+            on_hold: Box::new(on_hold),
+        })
+    }
+
+    /// Returns true if `entry` is enabled and either global or scoped to
+    /// whichever context is currently active (the top of the context stack).
+    fn is_active(entry: &ShortcutEntry, current_context: &Option<String>) -> bool {
+        entry.enabled && (entry.context.is_none() || entry.context == *current_context)
+    }
+
+    /// Returns true if `tail` (a slice of timestamped keys) matches `prefix`
+    /// key-for-key and every consecutive pair in `tail` falls within `timeout`
+    /// of the one before it.
+    fn tail_matches(tail: &[(Key, Instant)], prefix: &[Key], timeout: Duration) -> bool {
+        tail.iter().map(|(k, _)| k).eq(prefix.iter())
+            && tail
+                .windows(2)
+                .all(|pair| pair[1].1.duration_since(pair[0].1) <= timeout)
+    }
+
+    /// Fires the tap-dance handler registered for `count` taps on `key`, if any.
+    fn resolve_tap_dance(
+        shortcuts: &[ShortcutEntry],
+        current_context: &Option<String>,
+        key: Key,
+        count: u32,
+        end: TapDanceEnd,
+    ) {
+        for entry in shortcuts {
+            if !Self::is_active(entry, current_context) {
+                continue;
+            }
+            if let ShortcutType::TapDance {
+                key: dance_key,
+                handlers,
+                ..
+            } = &entry.shortcut_type
+            {
+                if *dance_key == key {
+                    if let Some(handler) = handlers.get(&count) {
+                        handler(end);
+                    }
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Fires the tap-hold "hold" handler for `key`, if any is registered.
+    fn resolve_tap_hold_as_hold(
+        shortcuts: &[ShortcutEntry],
+        current_context: &Option<String>,
+        key: Key,
+    ) {
+        for entry in shortcuts {
+            if !Self::is_active(entry, current_context) {
+                continue;
+            }
+            if let ShortcutType::TapHold {
+                key: dance_key,
+                on_hold,
+                ..
+            } = &entry.shortcut_type
+            {
+                if *dance_key == key {
+                    on_hold();
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Fires the tap-hold "tap" handler for `key`, if any is registered.
+    fn resolve_tap_hold_as_tap(
+        shortcuts: &[ShortcutEntry],
+        current_context: &Option<String>,
+        key: Key,
+    ) {
+        for entry in shortcuts {
+            if !Self::is_active(entry, current_context) {
+                continue;
+            }
+            if let ShortcutType::TapHold {
+                key: dance_key,
+                on_tap,
+                ..
+            } = &entry.shortcut_type
+            {
+                if *dance_key == key {
+                    on_tap();
+                    return;
+                }
+            }
+        }
     }
 
     /// Starts listening for keyboard events in a new thread.
     /// This function will not block the main thread.
+    ///
+    /// Uses `rdev::grab` rather than `rdev::listen` so that shortcuts
+    /// registered with `consume: true` can swallow their triggering event
+    /// instead of letting it also reach the focused application.
     pub fn start_listening(&self) {
         let shortcuts = Arc::clone(&self.shortcuts);
+        let context_stack = Arc::clone(&self.context_stack);
         let pressed_keys = Arc::clone(&self.pressed_keys);
         let last_key_press_time = Arc::clone(&self.last_key_press_time);
         let last_key_pressed = Arc::clone(&self.last_key_pressed);
+        let pending_sequence = Arc::clone(&self.pending_sequence);
+        let pending_tapdance = Arc::clone(&self.pending_tapdance);
+        let pending_taphold = Arc::clone(&self.pending_taphold);
+        let taphold_generation = Arc::clone(&self.taphold_generation);
+        let active_combinations = Arc::clone(&self.active_combinations);
 
         thread::spawn(move || {
-            listen(move |event| {
-                let mut pressed_keys = pressed_keys.lock().unwrap();
+            grab(move |event| {
+                let mut pressed_keys_guard = pressed_keys.lock().unwrap();
                 let mut last_key_press_time = last_key_press_time.lock().unwrap();
                 let mut last_key_pressed = last_key_pressed.lock().unwrap();
-                let shortcuts = shortcuts.lock().unwrap();
+                let shortcuts_guard = shortcuts.lock().unwrap();
+                let current_context = context_stack.lock().unwrap().last().cloned();
 
                 match event.event_type {
                     EventType::KeyPress(key) => {
                         // println!("Pressed: {:?}", key);
-                        pressed_keys.insert(key);
+                        let mut consume_event = false;
+                        let is_tap_hold_key = shortcuts_guard.iter().any(|entry| {
+                            Self::is_active(entry, &current_context)
+                                && matches!(entry.shortcut_type, ShortcutType::TapHold { key: k, .. } if k == key)
+                        });
+
+                        // A tap-hold key is not marked "pressed" right away, so it can't
+                        // spuriously satisfy an unrelated combination while its own tap
+                        // vs. hold outcome is still undecided.
+                        if !is_tap_hold_key {
+                            pressed_keys_guard.insert(key);
+                        }
+
+                        // Any tap-hold still pending on a *different* key is interrupted
+                        // by this press and resolves as a hold.
+                        {
+                            let mut pending = pending_taphold.lock().unwrap();
+                            let other_keys: Vec<Key> = pending
+                                .keys()
+                                .copied()
+                                .filter(|pending_key| *pending_key != key)
+                                .collect();
+                            for other_key in other_keys {
+                                pending.remove(&other_key);
+                                pressed_keys_guard.insert(other_key);
+                                Self::resolve_tap_hold_as_hold(
+                                    &shortcuts_guard,
+                                    &current_context,
+                                    other_key,
+                                );
+                            }
+
+                            if is_tap_hold_key
+                                && !pending.contains_key(&key)
+                                && !pressed_keys_guard.contains(&key)
+                            {
+                                let hold_threshold = shortcuts_guard
+                                    .iter()
+                                    .filter(|entry| Self::is_active(entry, &current_context))
+                                    .find_map(|entry| {
+                                        if let ShortcutType::TapHold {
+                                            key: k,
+                                            hold_threshold,
+                                            ..
+                                        } = &entry.shortcut_type
+                                        {
+                                            (*k == key).then_some(*hold_threshold)
+                                        } else {
+                                            None
+                                        }
+                                    })
+                                    .unwrap();
+
+                                let mut generation_counter = taphold_generation.lock().unwrap();
+                                *generation_counter += 1;
+                                let generation = *generation_counter;
+                                drop(generation_counter);
+
+                                pending.insert(key, TapHoldState { generation });
 
-                        // Check for combination shortcuts.
-                        for shortcut in shortcuts.iter() {
-                            if let ShortcutType::Combination(keys) = &shortcut.shortcut_type {
-                                if keys.is_subset(&pressed_keys) {
-                                    (shortcut.callback)();
+                                let shortcuts_for_timer = Arc::clone(&shortcuts);
+                                let context_stack_for_timer = Arc::clone(&context_stack);
+                                let pending_taphold_for_timer = Arc::clone(&pending_taphold);
+                                let pressed_keys_for_timer = Arc::clone(&pressed_keys);
+                                thread::spawn(move || {
+                                    thread::sleep(hold_threshold);
+                                    let mut pending = pending_taphold_for_timer.lock().unwrap();
+                                    if let Some(state) = pending.get(&key) {
+                                        if state.generation == generation {
+                                            pending.remove(&key);
+                                            drop(pending);
+                                            pressed_keys_for_timer.lock().unwrap().insert(key);
+                                            let shortcuts_guard =
+                                                shortcuts_for_timer.lock().unwrap();
+                                            let current_context =
+                                                context_stack_for_timer.lock().unwrap().last().cloned();
+                                            Self::resolve_tap_hold_as_hold(
+                                                &shortcuts_guard,
+                                                &current_context,
+                                                key,
+                                            );
+                                        }
+                                    }
+                                });
+                            }
+                            // A repeat KeyPress of an already-pending tap-hold key (OS
+                            // auto-repeat while held) is ignored here; the original timer
+                            // keeps running undisturbed. So is a repeat KeyPress of one that
+                            // has already resolved as a hold: it stays in `pressed_keys`
+                            // until released.
+                        }
+
+                        // Check for combination shortcuts. A combination that happens to
+                        // also be a prefix of a sequence still fires on every matching
+                        // press, independent of whatever the sequence buffer is doing.
+                        //
+                        // The OS keeps sending KeyPress events at its auto-repeat rate
+                        // while a combination's keys stay held; `active_combinations`
+                        // tracks which combinations are already satisfied so we only
+                        // re-fire on the configured `repeat` cadence (or not at all).
+                        {
+                            let mut active = active_combinations.lock().unwrap();
+                            for entry in shortcuts_guard.iter() {
+                                if !Self::is_active(entry, &current_context) {
+                                    continue;
+                                }
+                                if let ShortcutType::Combination {
+                                    keys,
+                                    consume,
+                                    repeat,
+                                    callback,
+                                } = &entry.shortcut_type
+                                {
+                                    if !keys.is_subset(&pressed_keys_guard) {
+                                        continue;
+                                    }
+
+                                    let now = Instant::now();
+                                    let should_fire = match active.get(&entry.id) {
+                                        None => true,
+                                        Some(last_fired) => repeat.is_some_and(|interval| {
+                                            now.duration_since(*last_fired) >= interval
+                                        }),
+                                    };
+
+                                    if should_fire {
+                                        callback();
+                                        active.insert(entry.id, now);
+                                    }
+                                    consume_event |= *consume && keys.contains(&key);
+                                }
+                            }
+                        }
+
+                        // Check for modifier-agnostic combinations. Like plain
+                        // `Combination`s, these only fire on the not-satisfied ->
+                        // satisfied transition, via the same `active_combinations`
+                        // tracking, so the OS's auto-repeat on the main key doesn't
+                        // re-trigger it for as long as it stays held.
+                        {
+                            let current_modifiers =
+                                ModifierSet::from_pressed_keys(&pressed_keys_guard);
+                            let mut active = active_combinations.lock().unwrap();
+                            for entry in shortcuts_guard.iter() {
+                                if !Self::is_active(entry, &current_context) {
+                                    continue;
+                                }
+                                if let ShortcutType::ModifierCombination {
+                                    modifiers,
+                                    key: main_key,
+                                    consume,
+                                    repeat,
+                                    callback,
+                                } = &entry.shortcut_type
+                                {
+                                    if *main_key == key
+                                        && modifiers.is_satisfied_by(&current_modifiers)
+                                    {
+                                        let now = Instant::now();
+                                        let should_fire = match active.get(&entry.id) {
+                                            None => true,
+                                            Some(last_fired) => repeat.is_some_and(|interval| {
+                                                now.duration_since(*last_fired) >= interval
+                                            }),
+                                        };
+
+                                        if should_fire {
+                                            callback();
+                                            active.insert(entry.id, now);
+                                        }
+                                        consume_event |= *consume;
+                                    }
                                 }
                             }
                         }
 
                         // Check for succession shortcuts.
                         if let Some(last_press) = *last_key_press_time {
-                            for shortcut in shortcuts.iter() {
+                            for entry in shortcuts_guard.iter() {
+                                if !Self::is_active(entry, &current_context) {
+                                    continue;
+                                }
                                 if let ShortcutType::Succession {
                                     key: succession_key,
                                     timeout,
-                                } = &shortcut.shortcut_type
+                                    consume,
+                                    callback,
+                                } = &entry.shortcut_type
                                 {
                                     // Check if the current key and the last key pressed are the same as the shortcut key,
                                     // and if the press is within the timeout.
@@ -108,23 +781,416 @@ impl KeyboardManager {
                                         && Some(*succession_key) == *last_key_pressed
                                         && last_press.elapsed() <= *timeout
                                     {
-                                        (shortcut.callback)();
+                                        callback();
+                                        consume_event |= *consume;
                                     }
                                 }
                             }
                         }
 
+                        // Check for sequence shortcuts. A tap-hold key is excluded
+                        // entirely, the same as it's excluded from `pressed_keys`: its
+                        // own tap-vs-hold outcome hasn't resolved yet, so it shouldn't
+                        // be able to complete an unrelated sequence in the meantime.
+                        if !is_tap_hold_key {
+                            let mut pending = pending_sequence.lock().unwrap();
+                            pending.push((key, Instant::now()));
+
+                            let mut fired = false;
+                            for entry in shortcuts_guard.iter() {
+                                if !Self::is_active(entry, &current_context) {
+                                    continue;
+                                }
+                                if let ShortcutType::Sequence {
+                                    keys,
+                                    timeout,
+                                    callback,
+                                } = &entry.shortcut_type
+                                {
+                                    if pending.len() >= keys.len() {
+                                        let start = pending.len() - keys.len();
+                                        if Self::tail_matches(&pending[start..], keys, *timeout) {
+                                            callback();
+                                            fired = true;
+                                            break;
+                                        }
+                                    }
+                                }
+                            }
+
+                            if fired {
+                                pending.clear();
+                            } else {
+                                // A sequence may have just been abandoned (wrong next key,
+                                // or the timeout lapsed). Rather than dropping the buffered
+                                // keystrokes, trim it down to the longest suffix that is
+                                // still a viable prefix of some registered sequence, so
+                                // those keystrokes remain live for whatever comes next.
+                                let mut keep = 0;
+                                for entry in shortcuts_guard.iter() {
+                                    if !Self::is_active(entry, &current_context) {
+                                        continue;
+                                    }
+                                    if let ShortcutType::Sequence { keys, timeout, .. } =
+                                        &entry.shortcut_type
+                                    {
+                                        let max_len = pending.len().min(keys.len());
+                                        for len in (1..=max_len).rev() {
+                                            let start = pending.len() - len;
+                                            if Self::tail_matches(
+                                                &pending[start..],
+                                                &keys[..len],
+                                                *timeout,
+                                            ) {
+                                                keep = keep.max(len);
+                                                break;
+                                            }
+                                        }
+                                    }
+                                }
+                                let drop_count = pending.len() - keep;
+                                if drop_count > 0 {
+                                    pending.drain(0..drop_count);
+                                }
+                            }
+                        }
+
+                        // Check for tap-dance shortcuts.
+                        {
+                            let mut pending = pending_tapdance.lock().unwrap();
+
+                            // Any dance pending on a different key is interrupted by this press.
+                            let other_keys: Vec<Key> = pending
+                                .keys()
+                                .copied()
+                                .filter(|pending_key| *pending_key != key)
+                                .collect();
+                            for other_key in other_keys {
+                                if let Some(state) = pending.remove(&other_key) {
+                                    Self::resolve_tap_dance(
+                                        &shortcuts_guard,
+                                        &current_context,
+                                        other_key,
+                                        state.count,
+                                        TapDanceEnd::OtherKey,
+                                    );
+                                }
+                            }
+
+                            if let Some(timeout) = shortcuts_guard
+                                .iter()
+                                .filter(|entry| Self::is_active(entry, &current_context))
+                                .find_map(|entry| {
+                                    if let ShortcutType::TapDance {
+                                        key: dance_key,
+                                        timeout,
+                                        ..
+                                    } = &entry.shortcut_type
+                                    {
+                                        (*dance_key == key).then_some(*timeout)
+                                    } else {
+                                        None
+                                    }
+                                })
+                            {
+                                // Generations come from one counter shared by every dance, so
+                                // a timer left over from a dance that a different key
+                                // interrupted can't match a newer dance on the same key.
+                                static TAPDANCE_GENERATION: std::sync::atomic::AtomicU64 =
+                                    std::sync::atomic::AtomicU64::new(0);
+                                let state = pending.entry(key).or_insert(TapDanceState {
+                                    count: 0,
+                                    generation: 0,
+                                });
+                                state.count += 1;
+                                state.generation = TAPDANCE_GENERATION
+                                    .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+                                    + 1;
+                                let generation = state.generation;
+
+                                let shortcuts_for_timer = Arc::clone(&shortcuts);
+                                let context_stack_for_timer = Arc::clone(&context_stack);
+                                let pending_tapdance_for_timer = Arc::clone(&pending_tapdance);
+                                thread::spawn(move || {
+                                    thread::sleep(timeout);
+                                    let mut pending = pending_tapdance_for_timer.lock().unwrap();
+                                    if let Some(state) = pending.get(&key) {
+                                        if state.generation == generation {
+                                            let count = state.count;
+                                            pending.remove(&key);
+                                            drop(pending);
+                                            let shortcuts_guard =
+                                                shortcuts_for_timer.lock().unwrap();
+                                            let current_context =
+                                                context_stack_for_timer.lock().unwrap().last().cloned();
+                                            Self::resolve_tap_dance(
+                                                &shortcuts_guard,
+                                                &current_context,
+                                                key,
+                                                count,
+                                                TapDanceEnd::Timeout,
+                                            );
+                                        }
+                                    }
+                                });
+                            }
+                        }
+
                         // Update the last key press time and key.
                         *last_key_press_time = Some(Instant::now());
                         *last_key_pressed = Some(key);
+
+                        if consume_event { None } else { Some(event) }
                     }
                     EventType::KeyRelease(key) => {
-                        pressed_keys.remove(&key);
+                        // A pending tap-hold resolves as a tap on release, provided it
+                        // hasn't already resolved as a hold (in which case it's no
+                        // longer in `pending_taphold` and just behaves like a normal key).
+                        let resolved_as_tap = {
+                            let mut pending = pending_taphold.lock().unwrap();
+                            pending.remove(&key).is_some()
+                        };
+                        if resolved_as_tap {
+                            Self::resolve_tap_hold_as_tap(&shortcuts_guard, &current_context, key);
+                        } else {
+                            pressed_keys_guard.remove(&key);
+                        }
+
+                        // Releasing any key of an active combination breaks it, so the
+                        // next time all its keys are held again counts as a fresh press.
+                        let mut active = active_combinations.lock().unwrap();
+                        active.retain(|id, _| {
+                            !shortcuts_guard.iter().any(|entry| {
+                                entry.id == *id
+                                    && match &entry.shortcut_type {
+                                        ShortcutType::Combination { keys, .. } => {
+                                            keys.contains(&key)
+                                        }
+                                        ShortcutType::ModifierCombination {
+                                            modifiers,
+                                            key: main_key,
+                                            ..
+                                        } => {
+                                            *main_key == key
+                                                || Modifier::from_key(key)
+                                                    .is_some_and(|m| modifiers.requires(m))
+                                        }
+                                        _ => false,
+                                    }
+                            })
+                        });
+
+                        Some(event)
                     }
-                    _ => {}
+                    _ => Some(event),
                 }
             })
             .expect("Failed to listen for keyboard events");
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(offset_ms: u64, base: Instant) -> Instant {
+        base + Duration::from_millis(offset_ms)
+    }
+
+    #[test]
+    fn tail_matches_requires_same_keys_in_order() {
+        let base = Instant::now();
+        let tail = [(Key::KeyG, at(0, base)), (Key::KeyJ, at(10, base))];
+        assert!(KeyboardManager::tail_matches(
+            &tail,
+            &[Key::KeyG, Key::KeyJ],
+            Duration::from_millis(400)
+        ));
+        assert!(!KeyboardManager::tail_matches(
+            &tail,
+            &[Key::KeyJ, Key::KeyG],
+            Duration::from_millis(400)
+        ));
+    }
+
+    #[test]
+    fn modifier_set_is_satisfied_by_ignores_left_right_side() {
+        let required = ModifierSet::new().with(Modifier::Meta);
+        let held_left = ModifierSet::from_pressed_keys(&HashSet::from([Key::MetaLeft]));
+        let held_right = ModifierSet::from_pressed_keys(&HashSet::from([Key::MetaRight]));
+        assert!(required.is_satisfied_by(&held_left));
+        assert!(required.is_satisfied_by(&held_right));
+    }
+
+    #[test]
+    fn modifier_set_is_satisfied_by_requires_every_modifier() {
+        let required = ModifierSet::new().with(Modifier::Meta).with(Modifier::Shift);
+        let meta_only = ModifierSet::from_pressed_keys(&HashSet::from([Key::MetaLeft]));
+        let both = ModifierSet::from_pressed_keys(&HashSet::from([Key::MetaLeft, Key::ShiftLeft]));
+        assert!(!required.is_satisfied_by(&meta_only));
+        assert!(required.is_satisfied_by(&both));
+    }
+
+    fn entry_with(context: Option<&str>, enabled: bool) -> ShortcutEntry {
+        ShortcutEntry {
+            id: 0,
+            context: context.map(str::to_string),
+            enabled,
+            shortcut_type: ShortcutType::Combination {
+                keys: HashSet::new(),
+                consume: false,
+                repeat: None,
+                callback: Box::new(|| {}),
+            },
+        }
+    }
+
+    #[test]
+    fn is_active_skips_disabled_entries() {
+        let entry = entry_with(None, false);
+        assert!(!KeyboardManager::is_active(&entry, &None));
+    }
+
+    #[test]
+    fn is_active_global_entry_matches_any_context() {
+        let entry = entry_with(None, true);
+        assert!(KeyboardManager::is_active(&entry, &None));
+        assert!(KeyboardManager::is_active(&entry, &Some("debug".to_string())));
+    }
+
+    #[test]
+    fn is_active_scoped_entry_only_matches_while_its_context_is_active() {
+        let entry = entry_with(Some("debug"), true);
+        assert!(KeyboardManager::is_active(
+            &entry,
+            &Some("debug".to_string())
+        ));
+        assert!(!KeyboardManager::is_active(
+            &entry,
+            &Some("normal".to_string())
+        ));
+    }
+
+    #[test]
+    fn is_active_scoped_entry_matches_nothing_once_its_context_is_popped() {
+        let entry = entry_with(Some("debug"), true);
+        assert!(!KeyboardManager::is_active(&entry, &None));
+    }
+
+    fn tapdance_entry(
+        key: Key,
+        handlers: HashMap<u32, Box<dyn Fn(TapDanceEnd) + Send + Sync>>,
+        enabled: bool,
+    ) -> ShortcutEntry {
+        ShortcutEntry {
+            id: 0,
+            context: None,
+            enabled,
+            shortcut_type: ShortcutType::TapDance {
+                key,
+                timeout: Duration::from_millis(250),
+                handlers,
+            },
+        }
+    }
+
+    #[test]
+    fn resolve_tap_dance_dispatches_to_the_matching_tap_count() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_for_handler = Arc::clone(&seen);
+        let mut handlers: HashMap<u32, Box<dyn Fn(TapDanceEnd) + Send + Sync>> = HashMap::new();
+        handlers.insert(
+            2,
+            Box::new(move |end| seen_for_handler.lock().unwrap().push(end)),
+        );
+        let shortcuts = vec![tapdance_entry(Key::KeyJ, handlers, true)];
+
+        KeyboardManager::resolve_tap_dance(&shortcuts, &None, Key::KeyJ, 2, TapDanceEnd::Timeout);
+
+        assert_eq!(*seen.lock().unwrap(), vec![TapDanceEnd::Timeout]);
+    }
+
+    #[test]
+    fn resolve_tap_dance_ignores_unregistered_counts_and_disabled_entries() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let seen_for_handler = Arc::clone(&seen);
+        let mut handlers: HashMap<u32, Box<dyn Fn(TapDanceEnd) + Send + Sync>> = HashMap::new();
+        handlers.insert(
+            1,
+            Box::new(move |end| seen_for_handler.lock().unwrap().push(end)),
+        );
+        let seen_for_disabled = Arc::clone(&seen);
+        let mut disabled_handlers: HashMap<u32, Box<dyn Fn(TapDanceEnd) + Send + Sync>> =
+            HashMap::new();
+        disabled_handlers.insert(
+            1,
+            Box::new(move |end| seen_for_disabled.lock().unwrap().push(end)),
+        );
+        let shortcuts = vec![
+            tapdance_entry(Key::KeyJ, handlers, true),
+            tapdance_entry(Key::KeyK, disabled_handlers, false),
+        ];
+
+        // J is enabled but has no handler for 2 taps, and K has a 1-tap handler
+        // but is disabled: neither should fire.
+        KeyboardManager::resolve_tap_dance(&shortcuts, &None, Key::KeyJ, 2, TapDanceEnd::OtherKey);
+        KeyboardManager::resolve_tap_dance(&shortcuts, &None, Key::KeyK, 1, TapDanceEnd::OtherKey);
+
+        assert!(seen.lock().unwrap().is_empty());
+    }
+
+    fn taphold_entry(key: Key, on_tap: Arc<Mutex<u32>>, on_hold: Arc<Mutex<u32>>) -> ShortcutEntry {
+        ShortcutEntry {
+            id: 0,
+            context: None,
+            enabled: true,
+            shortcut_type: ShortcutType::TapHold {
+                key,
+                hold_threshold: Duration::from_millis(200),
+                on_tap: Box::new(move || *on_tap.lock().unwrap() += 1),
+                on_hold: Box::new(move || *on_hold.lock().unwrap() += 1),
+            },
+        }
+    }
+
+    #[test]
+    fn resolve_tap_hold_as_tap_fires_only_on_tap() {
+        let taps = Arc::new(Mutex::new(0));
+        let holds = Arc::new(Mutex::new(0));
+        let shortcuts = vec![taphold_entry(Key::Space, Arc::clone(&taps), Arc::clone(&holds))];
+
+        KeyboardManager::resolve_tap_hold_as_tap(&shortcuts, &None, Key::Space);
+
+        assert_eq!(*taps.lock().unwrap(), 1);
+        assert_eq!(*holds.lock().unwrap(), 0);
+    }
+
+    #[test]
+    fn resolve_tap_hold_as_hold_fires_only_on_hold() {
+        let taps = Arc::new(Mutex::new(0));
+        let holds = Arc::new(Mutex::new(0));
+        let shortcuts = vec![taphold_entry(Key::Space, Arc::clone(&taps), Arc::clone(&holds))];
+
+        KeyboardManager::resolve_tap_hold_as_hold(&shortcuts, &None, Key::Space);
+
+        assert_eq!(*taps.lock().unwrap(), 0);
+        assert_eq!(*holds.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn tail_matches_rejects_gaps_over_timeout() {
+        let base = Instant::now();
+        let tail = [(Key::KeyG, at(0, base)), (Key::KeyG, at(500, base))];
+        assert!(!KeyboardManager::tail_matches(
+            &tail,
+            &[Key::KeyG, Key::KeyG],
+            Duration::from_millis(400)
+        ));
+        assert!(KeyboardManager::tail_matches(
+            &tail,
+            &[Key::KeyG, Key::KeyG],
+            Duration::from_millis(500)
+        ));
+    }
+}
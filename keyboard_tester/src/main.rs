@@ -1,7 +1,8 @@
 mod keyboard;
 
-use keyboard::KeyboardManager;
+use keyboard::{KeyboardManager, Modifier, ModifierSet, TapDanceEnd};
 use rdev::Key;
+use std::collections::HashMap;
 use std::thread;
 use std::time::Duration;
 
@@ -10,19 +11,101 @@ fn main() {
     let mut manager = KeyboardManager::new();
 
     // Register a succession shortcut for pressing Left Shift twice within 300ms.
-    manager.register_succession(Key::ShiftLeft, Duration::from_millis(300), || {
+    manager.register_succession(Key::ShiftLeft, Duration::from_millis(300), false, || {
         println!("Double Shift triggered!");
     });
 
     // Register a succession shortcut for pressing Control twice within 300ms.
-    manager.register_succession(Key::Unknown(62), Duration::from_millis(300), || {
+    manager.register_succession(Key::Unknown(62), Duration::from_millis(300), false, || {
         println!("Double Control triggered!");
     });
 
-    manager.register_combination(vec![Key::MetaLeft, Key::KeyS], || {
-        println!("'CMD+S' combination triggered!");
+    // Modifier-agnostic: fires for CMD+S whether it's the left or right Meta
+    // key held down. Consume it so the OS-level save dialog doesn't also pop up.
+    manager.register_modifier_combination(
+        ModifierSet::new().with(Modifier::Meta),
+        Key::KeyS,
+        true,
+        || {
+            println!("'CMD+S' combination triggered!");
+        },
+    );
+
+    // Keeps firing at a 150ms cadence for as long as CMD+= stays held, e.g.
+    // for a "zoom in" action that should repeat while the keys are down.
+    manager.register_combination_with_repeat(
+        vec![Key::MetaLeft, Key::Equal],
+        true,
+        Duration::from_millis(150),
+        || {
+            println!("'CMD+=' held, zooming in...");
+        },
+    );
+
+    // Same repeat behavior, but modifier-agnostic: fires for CTRL+Z on
+    // either side at a 120ms cadence for as long as it's held, e.g. for a
+    // "repeat undo" action.
+    manager.register_modifier_combination_with_repeat(
+        ModifierSet::new().with(Modifier::Control),
+        Key::KeyZ,
+        false,
+        Duration::from_millis(120),
+        || {
+            println!("'CTRL+Z' held, undoing repeatedly...");
+        },
+    );
+
+    // "g g" to jump to the top, Zed/Vim-style.
+    manager.register_sequence(vec![Key::KeyG, Key::KeyG], Duration::from_millis(400), || {
+        println!("'g g' sequence triggered!");
+    });
+
+    // Tapping J once moves down a line; a double-tap within 250ms moves down
+    // a paragraph instead.
+    let mut tapdance_handlers: HashMap<u32, Box<dyn Fn(TapDanceEnd) + Send + Sync>> =
+        HashMap::new();
+    tapdance_handlers.insert(1, Box::new(|end: TapDanceEnd| println!("J tapped once ({end:?})")));
+    tapdance_handlers.insert(
+        2,
+        Box::new(|end: TapDanceEnd| println!("J tapped twice ({end:?})")),
+    );
+    manager.register_tapdance(Key::KeyJ, Duration::from_millis(250), tapdance_handlers);
+
+    // Space-as-layer: a quick tap fires the tap handler, held past 200ms it
+    // fires the hold handler instead. The Space key events themselves still
+    // reach the focused application either way; holding it does not stop it
+    // typing a space.
+    manager.register_tap_hold(
+        Key::Space,
+        Duration::from_millis(200),
+        || println!("Space tapped!"),
+        || println!("Space held (layer active)!"),
+    );
+
+    // Contexts scope a shortcut to an exclusive mode: a shortcut registered
+    // while a context is on top of the stack only matches while that
+    // context stays active. "debug" stays pushed for the life of this demo,
+    // so pressing D is a real, observable effect the whole time it runs.
+    manager.push_context("debug");
+    manager.register_combination(vec![Key::KeyD], false, || {
+        println!("Debug dump triggered!");
     });
 
+    // Nested "debug-verbose" sub-mode: its shortcut only matches while it's
+    // the topmost context. Popping back to "debug" below means pressing V
+    // has no effect for the rest of the run, while D (above) still does.
+    manager.push_context("debug-verbose");
+    let verbose_dump = manager.register_combination(vec![Key::KeyV], false, || {
+        println!("Verbose dump triggered!");
+    });
+    manager.pop_context();
+
+    // Handles can be toggled or torn down later without touching the rest
+    // of the registered shortcuts; `verbose_dump` is already unreachable
+    // now that "debug-verbose" was popped, so tear it down too.
+    manager.set_enabled(verbose_dump, false);
+    manager.unregister(verbose_dump);
+
     println!("Listening for a double Shift press (Left Shift twice within 300ms)...");
 
     // Start listening for events in the background.